@@ -0,0 +1,5 @@
+//! This module provides the config models that are loaded from the app's configuration and
+//! handed down to the rest of the crate (for example, the list of upstream instances an engine
+//! should talk to).
+
+pub mod parser_models;
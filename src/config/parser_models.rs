@@ -0,0 +1,19 @@
+//! This module provides the config models for the individual upstream search engines.
+
+/// Config for the searx engine, namely the pool of upstream instance URLs it should query. A
+/// list rather than a single URL lets the engine fail over to the next instance when the one it
+/// just tried is rate-limiting, down, or returning a CAPTCHA.
+#[derive(Clone)]
+pub struct SearxConfig {
+    /// The base search URLs (e.g. `https://searx.work/search`) of the searx instances to query,
+    /// in the order they should be tried first.
+    pub instances: Vec<String>,
+}
+
+impl Default for SearxConfig {
+    fn default() -> Self {
+        SearxConfig {
+            instances: vec!["https://searx.work/search".to_string()],
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! This module provides functionality shared across the individual search engine modules, most
+//! notably a safe-search filter for upstream engines that have no native safe-search parameter
+//! of their own.
+
+use std::collections::HashMap;
+
+use crate::search_results_handler::aggregation_models::RawSearchResult;
+
+/// A small, bundled list of adult domains used to filter out results on upstream engines that
+/// don't expose a native safe-search toggle. This is intentionally conservative; an engine that
+/// already supports its own safe-search parameter (e.g. searx) should prefer that instead of
+/// relying on this filter.
+const ADULT_DOMAIN_BLOCKLIST: &[&str] = &[
+    "pornhub.com",
+    "xvideos.com",
+    "xnxx.com",
+    "xhamster.com",
+    "redtube.com",
+];
+
+/// Removes every result from `results` whose `visiting_url` host matches (or is a subdomain of)
+/// one of the domains on the bundled adult-domain blocklist. A no-op when `safe_search` is `0`
+/// (safe search disabled).
+pub fn filter_adult_results(results: &mut HashMap<String, RawSearchResult>, safe_search: u8) {
+    if safe_search == 0 {
+        return;
+    }
+
+    results.retain(|visiting_url, _| !is_adult_domain(visiting_url));
+}
+
+/// Returns true if `visiting_url`'s host matches, or is a subdomain of, one of the bundled
+/// adult domains.
+fn is_adult_domain(visiting_url: &str) -> bool {
+    let host = match url::Url::parse(visiting_url) {
+        Ok(parsed) => match parsed.host_str() {
+            Some(host) => host.to_lowercase(),
+            None => return false,
+        },
+        Err(_) => return false,
+    };
+
+    ADULT_DOMAIN_BLOCKLIST
+        .iter()
+        .any(|blocked| host == *blocked || host.ends_with(&format!(".{blocked}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(visiting_url: &str) -> RawSearchResult {
+        RawSearchResult::new(
+            "title".to_string(),
+            visiting_url.to_string(),
+            "description".to_string(),
+            vec!["test".to_string()],
+        )
+    }
+
+    #[test]
+    fn is_adult_domain_matches_an_exact_blocklisted_host() {
+        assert!(is_adult_domain("https://pornhub.com/foo"));
+    }
+
+    #[test]
+    fn is_adult_domain_matches_a_subdomain_of_a_blocklisted_host() {
+        assert!(is_adult_domain("https://www.pornhub.com/foo"));
+    }
+
+    #[test]
+    fn is_adult_domain_is_case_insensitive() {
+        assert!(is_adult_domain("https://WWW.PornHub.COM/foo"));
+    }
+
+    #[test]
+    fn is_adult_domain_does_not_match_a_lookalike_host() {
+        // "notpornhub.com" shares a suffix with "pornhub.com" but is a distinct domain, not a
+        // subdomain of it, and must not be blocked.
+        assert!(!is_adult_domain("https://notpornhub.com/foo"));
+    }
+
+    #[test]
+    fn is_adult_domain_does_not_match_an_unrelated_host() {
+        assert!(!is_adult_domain("https://example.com/foo"));
+    }
+
+    #[test]
+    fn filter_adult_results_is_a_no_op_when_safe_search_is_zero() {
+        let mut results = HashMap::new();
+        results.insert("https://pornhub.com/foo".to_string(), raw("https://pornhub.com/foo"));
+
+        filter_adult_results(&mut results, 0);
+
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn filter_adult_results_drops_only_the_blocklisted_entries() {
+        let mut results = HashMap::new();
+        results.insert(
+            "https://pornhub.com/foo".to_string(),
+            raw("https://pornhub.com/foo"),
+        );
+        results.insert(
+            "https://example.com/foo".to_string(),
+            raw("https://example.com/foo"),
+        );
+
+        filter_adult_results(&mut results, 1);
+
+        assert_eq!(results.len(), 1);
+        assert!(results.contains_key("https://example.com/foo"));
+    }
+}
@@ -0,0 +1,98 @@
+//! This module provides the error enum to handle different errors associated while requesting
+//! data from the upstream search engines with the user provided query and other parameters. It
+//! also provides the `SearchEngine` trait that every upstream search engine implements so the
+//! aggregator can select and fan out over whichever engines are enabled at runtime.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use error_stack::{IntoReport, Result, ResultExt};
+use reqwest::header::HeaderMap;
+
+use crate::search_results_handler::aggregation_models::RawSearchResult;
+
+/// A custom error type used for handling the different errors that can occur while requesting
+/// data from the upstream search engines.
+#[derive(Debug)]
+pub enum EngineError {
+    /// This variant handles all request related errors like forbidden, not found, etc.
+    RequestError,
+    /// This variant handles the errors which occurs when there are no results found on the
+    /// upstream search engine for the user provided query.
+    EmptyResultSet,
+    /// This variant handles all the errors which are not related to `RequestError` and
+    /// `EmptyResultSet` (for example: failure in initializing a scraping selector).
+    UnexpectedError,
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::RequestError => write!(
+                f,
+                "Error occurred while requesting data from upstream search engine"
+            ),
+            EngineError::EmptyResultSet => {
+                write!(f, "The upstream search engine returned an empty result set")
+            }
+            EngineError::UnexpectedError => {
+                write!(f, "An unexpected error occurred while processing the results")
+            }
+        }
+    }
+}
+
+impl error_stack::Context for EngineError {}
+
+/// A trait implemented by every upstream search engine so the aggregator can select and fan out
+/// over whichever engines are enabled in the config at runtime, instead of hardcoding each one
+/// by name.
+#[async_trait]
+pub trait SearchEngine: Send + Sync {
+    /// Fetches and parses a page of search results for `query` from this engine's upstream.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Takes the user provided query to query to the upstream search engine with.
+    /// * `page` - Takes an u32 as an argument.
+    /// * `user_agent` - Takes a random user agent string as an argument.
+    /// * `client` - Takes a shared, pooled `reqwest::Client` so the engine doesn't have to pay
+    /// the cost of a fresh connection pool, TLS config and DNS resolver on every request.
+    /// * `safe_search` - Takes the safe search level (0-4) to apply to the search.
+    /// * `request_timeout` - Takes the number of seconds to wait for the upstream to respond
+    /// before giving up, so a single slow upstream can't stall the whole aggregation.
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        user_agent: &str,
+        client: &reqwest::Client,
+        safe_search: u8,
+        request_timeout: u8,
+    ) -> Result<HashMap<String, RawSearchResult>, EngineError>;
+
+    /// Shared boilerplate for fetching a page of html from an upstream search engine, so each
+    /// engine implementation doesn't have to duplicate the request/timeout/error-mapping dance.
+    async fn fetch_html_from_upstream(
+        &self,
+        url: &str,
+        header_map: HeaderMap,
+        client: &reqwest::Client,
+        request_timeout: u8,
+    ) -> Result<String, EngineError> {
+        client
+            .get(url)
+            .headers(header_map)
+            .timeout(Duration::from_secs(request_timeout.into()))
+            .send()
+            .await
+            .into_report()
+            .change_context(EngineError::RequestError)?
+            .text()
+            .await
+            .into_report()
+            .change_context(EngineError::RequestError)
+    }
+}
@@ -7,9 +7,34 @@ pub mod bing;
 pub mod brave;
 pub mod common;
 pub mod duckduckgo;
+pub mod engine_models;
 pub mod librex;
 pub mod mojeek;
 pub mod search_result_parser;
 pub mod searx;
 pub mod startpage;
 pub mod wikipedia;
+
+use std::collections::HashMap;
+
+use error_stack::Result;
+
+use crate::config::parser_models::SearxConfig;
+
+use self::engine_models::{EngineError, SearchEngine};
+use self::searx::Searx;
+
+/// Builds the registry of upstream search engines, keyed by name, that the aggregator fans out
+/// searches over. Only engines enabled in the config should be selected out of this map at
+/// request time.
+///
+/// # Errors
+///
+/// Returns an `EngineError` if any of the engines fail to initialize (e.g. a bad CSS selector).
+pub fn search_engine_registry(
+    searx_config: &SearxConfig,
+) -> Result<HashMap<String, Box<dyn SearchEngine>>, EngineError> {
+    let mut engines: HashMap<String, Box<dyn SearchEngine>> = HashMap::new();
+    engines.insert("searx".to_string(), Box::new(Searx::new(searx_config)?));
+    Ok(engines)
+}
@@ -0,0 +1,206 @@
+//! This module provides a reusable parser that compiles the CSS selectors an engine needs once
+//! up front, so a single malformed or drifted result on the page is skipped instead of
+//! panicking the whole request.
+
+use error_stack::{Report, ResultExt};
+use scraper::{ElementRef, Html, Selector};
+
+use super::engine_models::EngineError;
+
+/// A struct that stores the selectors needed to scrape results off of an upstream search
+/// engine's results page.
+pub struct SearchResultParser {
+    no_result: Selector,
+    no_result_message: String,
+    results: Selector,
+    result_title: Selector,
+    result_url: Selector,
+    result_desc: Selector,
+}
+
+impl SearchResultParser {
+    /// Parses and compiles the five selectors an engine needs, returning `EngineError` should
+    /// any of them fail to compile instead of leaving that discovery to a later panic.
+    ///
+    /// # Arguments
+    ///
+    /// * `no_result` - The selector that matches the upstream's "no results found" message.
+    /// * `no_result_message` - The exact text of the upstream's "no results found" message.
+    /// Any other banner matched by `no_result` (e.g. a CAPTCHA or rate-limit notice) is treated
+    /// as a real upstream error rather than "no results for this query".
+    /// * `results` - The selector that matches a single result block.
+    /// * `result_title` - The selector (relative to a result block) that matches the title.
+    /// * `result_url` - The selector (relative to a result block) that matches the anchor
+    /// holding the `href`.
+    /// * `result_desc` - The selector (relative to a result block) that matches the description.
+    pub fn new(
+        no_result: &str,
+        no_result_message: &str,
+        results: &str,
+        result_title: &str,
+        result_url: &str,
+        result_desc: &str,
+    ) -> Result<SearchResultParser, Report<EngineError>> {
+        Ok(SearchResultParser {
+            no_result: SearchResultParser::new_selector(no_result)?,
+            no_result_message: no_result_message.to_string(),
+            results: SearchResultParser::new_selector(results)?,
+            result_title: SearchResultParser::new_selector(result_title)?,
+            result_url: SearchResultParser::new_selector(result_url)?,
+            result_desc: SearchResultParser::new_selector(result_desc)?,
+        })
+    }
+
+    /// A helper function which compiles a single CSS selector string, attaching a printable
+    /// message naming the offending selector on failure.
+    fn new_selector(selector: &str) -> Result<Selector, Report<EngineError>> {
+        Selector::parse(selector)
+            .map_err(|_| Report::new(EngineError::UnexpectedError))
+            .attach_printable_lazy(|| format!("invalid CSS selector: {selector}"))
+    }
+
+    /// Returns true if the document contains the upstream's "no results found" message. The
+    /// banner is duplicated on the page (hence `nth(1)` rather than the first match). The text
+    /// is compared against `no_result_message` exactly, so a CAPTCHA, rate-limit, or other
+    /// dialog-error banner with different wording is not misclassified as "no results" and
+    /// instead surfaces as a real upstream error.
+    pub fn parse_for_no_results(&self, document: &Html) -> bool {
+        document
+            .select(&self.no_result)
+            .nth(1)
+            .is_some_and(|element| element.inner_html() == self.no_result_message)
+    }
+
+    /// Selects every result block on the page and hands each one to `build_result`, keeping
+    /// only the ones that produce a value. A result that is missing a title, url or
+    /// description (i.e. `build_result` returns `None`) is simply skipped rather than
+    /// unwrapping and panicking the whole request.
+    pub fn parse_for_results<T>(
+        &self,
+        document: &Html,
+        mut build_result: impl FnMut(&ElementRef<'_>) -> Option<T>,
+    ) -> Vec<T> {
+        document
+            .select(&self.results)
+            .filter_map(|result| build_result(&result))
+            .collect()
+    }
+
+    /// Returns the trimmed inner html of the first element (relative to `element`) matched by
+    /// `self.result_title`, or `None` if it is missing.
+    pub fn parse_title(&self, element: &ElementRef<'_>) -> Option<String> {
+        Self::parse_text(element, &self.result_title)
+    }
+
+    /// Returns the `href` attribute of the first element (relative to `element`) matched by
+    /// `self.result_url`, or `None` if it is missing.
+    pub fn parse_url(&self, element: &ElementRef<'_>) -> Option<String> {
+        Self::parse_attr(element, &self.result_url, "href")
+    }
+
+    /// Returns the trimmed inner html of the first element (relative to `element`) matched by
+    /// `self.result_desc`, or `None` if it is missing.
+    pub fn parse_description(&self, element: &ElementRef<'_>) -> Option<String> {
+        Self::parse_text(element, &self.result_desc)
+    }
+
+    /// Returns the trimmed inner html of the first element matched by `selector`, or `None` if
+    /// no element matches.
+    fn parse_text(element: &ElementRef<'_>, selector: &Selector) -> Option<String> {
+        Some(element.select(selector).next()?.inner_html().trim().to_string())
+    }
+
+    /// Returns the value of `attr` on the first element matched by `selector`, or `None` if no
+    /// element matches or the attribute is absent.
+    fn parse_attr(element: &ElementRef<'_>, selector: &Selector, attr: &str) -> Option<String> {
+        Some(
+            element
+                .select(selector)
+                .next()?
+                .value()
+                .attr(attr)?
+                .to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NO_RESULT_MESSAGE: &str =
+        "we didn't find any results. Please use another query or search in more categories";
+
+    fn test_parser() -> SearchResultParser {
+        SearchResultParser::new(
+            "#urls>.dialog-error>p",
+            NO_RESULT_MESSAGE,
+            ".result",
+            "h3>a",
+            "h3>a",
+            ".content",
+        )
+        .expect("selectors are valid")
+    }
+
+    #[test]
+    fn parse_for_results_skips_a_result_missing_a_required_field_instead_of_panicking() {
+        let parser = test_parser();
+        let document = Html::parse_fragment(
+            r#"
+            <div class="result"><h3><a href="https://a.example">Title A</a></h3><p class="content">Desc A</p></div>
+            <div class="result"><h3><a href="https://b.example">Title B</a></h3></div>
+            "#,
+        );
+
+        let titles: Vec<String> = parser
+            .parse_for_results(&document, |result| {
+                let title = parser.parse_title(result)?;
+                let _url = parser.parse_url(result)?;
+                let _description = parser.parse_description(result)?;
+                Some(title)
+            })
+            .into_iter()
+            .collect();
+
+        // The second result is missing a `.content` description, so it's skipped rather than
+        // unwrapping `None` and panicking the whole request.
+        assert_eq!(titles, vec!["Title A".to_string()]);
+    }
+
+    #[test]
+    fn parse_for_no_results_matches_only_the_exact_no_result_message() {
+        let parser = test_parser();
+
+        let no_results_document = Html::parse_fragment(&format!(
+            r#"<div id="urls">
+                <div class="dialog-error"><p>first banner</p></div>
+                <div class="dialog-error"><p>{NO_RESULT_MESSAGE}</p></div>
+            </div>"#
+        ));
+        assert!(parser.parse_for_no_results(&no_results_document));
+
+        // A CAPTCHA/rate-limit banner is also matched by the `#urls>.dialog-error>p` selector,
+        // but must not be misclassified as "no results for this query" just because it's the
+        // second dialog-error element on the page.
+        let captcha_document = Html::parse_fragment(
+            r#"<div id="urls">
+                <div class="dialog-error"><p>first banner</p></div>
+                <div class="dialog-error"><p>please verify you are not a robot</p></div>
+            </div>"#,
+        );
+        assert!(!parser.parse_for_no_results(&captcha_document));
+    }
+
+    #[test]
+    fn parse_for_no_results_is_false_without_a_second_banner() {
+        let parser = test_parser();
+        let document = Html::parse_fragment(&format!(
+            r#"<div id="urls">
+                <div class="dialog-error"><p>{NO_RESULT_MESSAGE}</p></div>
+            </div>"#
+        ));
+
+        assert!(!parser.parse_for_no_results(&document));
+    }
+}
@@ -1,138 +1,276 @@
-//! The `searx` module handles the scraping of results from the searx search engine instance
-//! by querying the upstream searx search engine instance with user provided query and with a page
-//! number if provided.
+//! The `searx` module handles the scraping of results from one of a configurable pool of searx
+//! search engine instances by querying the upstream instance with user provided query and with
+//! a page number if provided. When an instance is rate-limiting, down, or returns a CAPTCHA, the
+//! next instance in the pool is tried before giving up.
 
+use async_trait::async_trait;
 use reqwest::header::{HeaderMap, CONTENT_TYPE, COOKIE, REFERER, USER_AGENT};
-use scraper::{Html, Selector};
+use scraper::Html;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
 
-use crate::search_results_handler::aggregation_models::RawSearchResult;
+use crate::config::parser_models::SearxConfig;
+use crate::search_results_handler::aggregation_models::{insert_canonicalized, RawSearchResult};
 
-use super::engine_models::EngineError;
+use super::common::filter_adult_results;
+use super::engine_models::{EngineError, SearchEngine};
+use super::search_result_parser::SearchResultParser;
 use error_stack::{IntoReport, Report, Result, ResultExt};
 
-/// This function scrapes results from the upstream engine duckduckgo and puts all the scraped
-/// results like title, visiting_url (href in html),engine (from which engine it was fetched from)
-/// and description in a RawSearchResult and then adds that to HashMap whose keys are url and
-/// values are RawSearchResult struct and then returns it within a Result enum.
-///
-/// # Arguments
-///
-/// * `query` - Takes the user provided query to query to the upstream search engine with.
-/// * `page` - Takes an u32 as an argument.
-/// * `user_agent` - Takes a random user agent string as an argument.
-///
-/// # Errors
-///
-/// Returns an `EngineErrorKind` if the user is not connected to the internet or if their is failure to
-/// reach the above `upstream search engine` page or if the `upstream search engine` is unable to
-/// provide results for the requested search query and also returns error if the scraping selector
-/// or HeaderMap fails to initialize.
-pub async fn results(
-    query: &str,
-    page: u32,
-    user_agent: &str,
-) -> Result<HashMap<String, RawSearchResult>, EngineError> {
-    // Page number can be missing or empty string and so appropriate handling is required
-    // so that upstream server recieves valid page number.
-    let url: String = format!("https://searx.work/search?q={query}&pageno={page}");
-
-    // initializing headers and adding appropriate headers.
-    let mut header_map = HeaderMap::new();
-    header_map.insert(
-        USER_AGENT,
-        user_agent
-            .parse()
-            .into_report()
-            .change_context(EngineError::UnexpectedError)?,
-    );
-    header_map.insert(
-        REFERER,
-        "https://google.com/"
-            .parse()
-            .into_report()
-            .change_context(EngineError::UnexpectedError)?,
-    );
-    header_map.insert(
-        CONTENT_TYPE,
-        "application/x-www-form-urlencoded"
-            .parse()
-            .into_report()
-            .change_context(EngineError::UnexpectedError)?,
-    );
-    header_map.insert(COOKIE, "categories=general; language=auto; locale=en; autocomplete=duckduckgo; image_proxy=1; method=POST; safesearch=2; theme=simple; results_on_new_tab=1; doi_resolver=oadoi.org; simple_style=auto; center_alignment=1; query_in_title=1; infinite_scroll=0; disabled_engines=; enabled_engines=\"archive is__general\\054yep__general\\054curlie__general\\054currency__general\\054ddg definitions__general\\054wikidata__general\\054duckduckgo__general\\054tineye__general\\054lingva__general\\054startpage__general\\054yahoo__general\\054wiby__general\\054marginalia__general\\054alexandria__general\\054wikibooks__general\\054wikiquote__general\\054wikisource__general\\054wikiversity__general\\054wikivoyage__general\\054dictzone__general\\054seznam__general\\054mojeek__general\\054naver__general\\054wikimini__general\\054brave__general\\054petalsearch__general\\054goo__general\"; disabled_plugins=; enabled_plugins=\"searx.plugins.hostname_replace\\054searx.plugins.oa_doi_rewrite\\054searx.plugins.vim_hotkeys\"; tokens=; maintab=on; enginetab=on".parse().into_report().change_context(EngineError::UnexpectedError)?);
-
-    // fetch the html from upstream searx instance engine
-    let results: String = reqwest::Client::new()
-        .get(url)
-        .headers(header_map) // add spoofed headers to emulate human behaviours.
-        .send()
-        .await
-        .into_report()
-        .change_context(EngineError::RequestError)?
-        .text()
-        .await
-        .into_report()
-        .change_context(EngineError::RequestError)?;
-
-    let document: Html = Html::parse_document(&results);
-
-    let no_result: Selector = Selector::parse("#urls>.dialog-error>p")
-        .map_err(|_| Report::new(EngineError::UnexpectedError))
-        .attach_printable_lazy(|| format!("invalid CSS selector: {}", "#urls>.dialog-error>p"))?;
-
-    if let Some(no_result_msg) = document.select(&no_result).nth(1) {
-        if no_result_msg.inner_html()
-            == "we didn't find any results. Please use another query or search in more categories"
-        {
+/// A single searx instance tracked alongside how many times it has failed this session, so
+/// repeatedly-failing instances are tried last rather than first.
+struct SearxInstance {
+    /// The base search URL of the instance, e.g. `https://searx.work/search`.
+    base_url: String,
+    /// How many times a request against this instance has failed (or come back empty) this
+    /// session.
+    failure_count: AtomicU32,
+}
+
+/// A struct to handle the scraping of results from a pool of upstream searx search engine
+/// instances.
+pub struct Searx {
+    /// The parser holding the CSS selectors needed to scrape a searx results page.
+    parser: SearchResultParser,
+    /// The pool of instances to query, tried in order of ascending failure count.
+    instances: Vec<SearxInstance>,
+}
+
+impl Searx {
+    /// Constructs a new `Searx` from the configured instance pool, compiling the selectors it
+    /// needs up front so a bad selector is caught here rather than deep inside a request.
+    pub fn new(config: &SearxConfig) -> Result<Self, EngineError> {
+        Ok(Self {
+            parser: SearchResultParser::new(
+                "#urls>.dialog-error>p",
+                "we didn't find any results. Please use another query or search in more categories",
+                ".result",
+                "h3>a",
+                "h3>a",
+                ".content",
+            )?,
+            instances: config
+                .instances
+                .iter()
+                .map(|base_url| SearxInstance {
+                    base_url: base_url.clone(),
+                    failure_count: AtomicU32::new(0),
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns the configured instances ordered by ascending failure count, so the
+    /// least-failing instance is tried first.
+    fn instances_ordered_by_failures(&self) -> Vec<&SearxInstance> {
+        let mut instance_order: Vec<&SearxInstance> = self.instances.iter().collect();
+        instance_order.sort_by_key(|instance| instance.failure_count.load(Ordering::Relaxed));
+        instance_order
+    }
+
+    /// Queries a single instance and scrapes its results.
+    async fn results_from_instance(
+        &self,
+        instance: &SearxInstance,
+        query: &str,
+        page: u32,
+        user_agent: &str,
+        client: &reqwest::Client,
+        safe_search_level: u8,
+        request_timeout: u8,
+    ) -> Result<HashMap<String, RawSearchResult>, EngineError> {
+        let base_url = &instance.base_url;
+
+        // The page number is passed to the upstream as-is in the `pageno` query parameter.
+        let url: String =
+            format!("{base_url}?q={query}&pageno={page}&safesearch={safe_search_level}");
+
+        // initializing headers and adding appropriate headers.
+        let mut header_map = HeaderMap::new();
+        header_map.insert(
+            USER_AGENT,
+            user_agent
+                .parse()
+                .into_report()
+                .change_context(EngineError::UnexpectedError)?,
+        );
+        header_map.insert(
+            REFERER,
+            "https://google.com/"
+                .parse()
+                .into_report()
+                .change_context(EngineError::UnexpectedError)?,
+        );
+        header_map.insert(
+            CONTENT_TYPE,
+            "application/x-www-form-urlencoded"
+                .parse()
+                .into_report()
+                .change_context(EngineError::UnexpectedError)?,
+        );
+        header_map.insert(COOKIE, format!("categories=general; language=auto; locale=en; autocomplete=duckduckgo; image_proxy=1; method=POST; safesearch={safe_search_level}; theme=simple; results_on_new_tab=1; doi_resolver=oadoi.org; simple_style=auto; center_alignment=1; query_in_title=1; infinite_scroll=0; disabled_engines=; enabled_engines=\"archive is__general\\054yep__general\\054curlie__general\\054currency__general\\054ddg definitions__general\\054wikidata__general\\054duckduckgo__general\\054tineye__general\\054lingva__general\\054startpage__general\\054yahoo__general\\054wiby__general\\054marginalia__general\\054alexandria__general\\054wikibooks__general\\054wikiquote__general\\054wikisource__general\\054wikiversity__general\\054wikivoyage__general\\054dictzone__general\\054seznam__general\\054mojeek__general\\054naver__general\\054wikimini__general\\054brave__general\\054petalsearch__general\\054goo__general\"; disabled_plugins=; enabled_plugins=\"searx.plugins.hostname_replace\\054searx.plugins.oa_doi_rewrite\\054searx.plugins.vim_hotkeys\"; tokens=; maintab=on; enginetab=on").parse().into_report().change_context(EngineError::UnexpectedError)?);
+
+        // fetch the html from upstream searx instance engine, sharing the request/timeout
+        // boilerplate via the trait's default method.
+        let results: String = self
+            .fetch_html_from_upstream(&url, header_map, client, request_timeout)
+            .await?;
+
+        let document: Html = Html::parse_document(&results);
+
+        if self.parser.parse_for_no_results(&document) {
             return Err(Report::new(EngineError::EmptyResultSet));
         }
-    }
 
-    let results: Selector = Selector::parse(".result")
-        .map_err(|_| Report::new(EngineError::UnexpectedError))
-        .attach_printable_lazy(|| format!("invalid CSS selector: {}", ".result"))?;
-    let result_title: Selector = Selector::parse("h3>a")
-        .map_err(|_| Report::new(EngineError::UnexpectedError))
-        .attach_printable_lazy(|| format!("invalid CSS selector: {}", "h3>a"))?;
-    let result_url: Selector = Selector::parse("h3>a")
-        .map_err(|_| Report::new(EngineError::UnexpectedError))
-        .attach_printable_lazy(|| format!("invalid CSS selector: {}", "h3>a"))?;
-
-    let result_desc: Selector = Selector::parse(".content")
-        .map_err(|_| Report::new(EngineError::UnexpectedError))
-        .attach_printable_lazy(|| format!("invalid CSS selector: {}", ".content"))?;
-
-    // scrape all the results from the html
-    Ok(document
-        .select(&results)
-        .map(|result| {
-            RawSearchResult::new(
-                result
-                    .select(&result_title)
-                    .next()
-                    .unwrap()
-                    .inner_html()
-                    .trim()
-                    .to_string(),
-                result
-                    .select(&result_url)
-                    .next()
-                    .unwrap()
-                    .value()
-                    .attr("href")
-                    .unwrap()
-                    .to_string(),
-                result
-                    .select(&result_desc)
-                    .next()
-                    .unwrap()
-                    .inner_html()
-                    .trim()
-                    .to_string(),
+        // scrape all the results from the html, skipping any result that is missing a title,
+        // url or description instead of panicking the whole request.
+        let mut results_map: HashMap<String, RawSearchResult> = HashMap::new();
+        for search_result in self.parser.parse_for_results(&document, |result| {
+            Some(RawSearchResult::new(
+                self.parser.parse_title(result)?,
+                self.parser.parse_url(result)?,
+                self.parser.parse_description(result)?,
                 vec!["searx".to_string()],
-            )
-        })
-        .map(|search_result| (search_result.visiting_url.clone(), search_result))
-        .collect())
+            ))
+        }) {
+            insert_canonicalized(&mut results_map, search_result);
+        }
+
+        Ok(results_map)
+    }
+}
+
+#[async_trait]
+impl SearchEngine for Searx {
+    /// This function scrapes results from the upstream engine searx and puts all the scraped
+    /// results like title, visiting_url (href in html),engine (from which engine it was fetched from)
+    /// and description in a RawSearchResult and then adds that to HashMap whose keys are the
+    /// canonicalized url and values are RawSearchResult struct and then returns it within a Result enum. When an
+    /// instance errors out or comes back with an empty/`dialog-error` body, the next
+    /// least-failing instance in the pool is tried before giving up.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Takes the user provided query to query to the upstream search engine with.
+    /// * `page` - Takes an u32 as an argument.
+    /// * `user_agent` - Takes a random user agent string as an argument.
+    /// * `client` - Takes a shared, pooled `reqwest::Client` so the engine doesn't have to pay
+    /// the cost of a fresh connection pool, TLS config and DNS resolver on every request.
+    /// * `safe_search` - Takes the safe search level (0-4) to apply to the search. Searx only
+    /// supports levels 0-2 natively, so levels 3 and 4 are clamped down to 2.
+    /// * `request_timeout` - Takes the number of seconds to wait for the upstream to respond
+    /// before giving up, so a single slow upstream can't stall the whole aggregation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EngineError` if the user is not connected to the internet or if their is failure to
+    /// reach the above `upstream search engine` page or if the `upstream search engine` is unable to
+    /// provide results for the requested search query and also returns error if the scraping selector
+    /// or HeaderMap fails to initialize.
+    async fn results(
+        &self,
+        query: &str,
+        page: u32,
+        user_agent: &str,
+        client: &reqwest::Client,
+        safe_search: u8,
+        request_timeout: u8,
+    ) -> Result<HashMap<String, RawSearchResult>, EngineError> {
+        // searx only supports safe search levels 0 through 2, so anything above that is
+        // clamped down to the strictest level it natively understands.
+        let safe_search_level: u8 = std::cmp::min(safe_search, 2);
+
+        // Try the least-failing instance first, falling through to the next one on error so a
+        // single down/rate-limited/CAPTCHA'd instance doesn't fail the whole search.
+        let mut last_error = None;
+        for instance in self.instances_ordered_by_failures() {
+            match self
+                .results_from_instance(
+                    instance,
+                    query,
+                    page,
+                    user_agent,
+                    client,
+                    safe_search_level,
+                    request_timeout,
+                )
+                .await
+            {
+                // An empty result set (e.g. a results page with zero `.result` elements that
+                // wasn't caught by the dialog-error check) is just as much a sign of a broken
+                // instance as a hard error, so it's treated the same way: fall through to the
+                // next instance instead of returning a bogus "no results" straight away.
+                Ok(results) if results.is_empty() => {
+                    instance.failure_count.fetch_add(1, Ordering::Relaxed);
+                    last_error = Some(Report::new(EngineError::EmptyResultSet));
+                }
+                Ok(mut results) => {
+                    // searx has its own native safesearch parameter, but the shared filter is
+                    // applied as a defense-in-depth backstop in case the upstream's own
+                    // filtering misses something.
+                    filter_adult_results(&mut results, safe_search);
+                    return Ok(results);
+                }
+                Err(error) => {
+                    instance.failure_count.fetch_add(1, Ordering::Relaxed);
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| Report::new(EngineError::EmptyResultSet)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_searx(instance_urls: &[&str]) -> Searx {
+        let config = SearxConfig {
+            instances: instance_urls.iter().map(|url| url.to_string()).collect(),
+        };
+        Searx::new(&config).expect("selectors are valid")
+    }
+
+    #[test]
+    fn orders_instances_by_ascending_failure_count() {
+        let searx = test_searx(&[
+            "https://a.example/search",
+            "https://b.example/search",
+            "https://c.example/search",
+        ]);
+
+        searx.instances[0].failure_count.store(3, Ordering::Relaxed);
+        searx.instances[1].failure_count.store(0, Ordering::Relaxed);
+        searx.instances[2].failure_count.store(1, Ordering::Relaxed);
+
+        let ordered: Vec<&str> = searx
+            .instances_ordered_by_failures()
+            .into_iter()
+            .map(|instance| instance.base_url.as_str())
+            .collect();
+
+        assert_eq!(
+            ordered,
+            vec![
+                "https://b.example/search",
+                "https://c.example/search",
+                "https://a.example/search",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_fresh_instance_pool_is_tried_in_configured_order() {
+        let searx = test_searx(&["https://a.example/search", "https://b.example/search"]);
+
+        let ordered: Vec<&str> = searx
+            .instances_ordered_by_failures()
+            .into_iter()
+            .map(|instance| instance.base_url.as_str())
+            .collect();
+
+        assert_eq!(ordered, vec!["https://a.example/search", "https://b.example/search"]);
+    }
 }
@@ -0,0 +1,182 @@
+//! This module provides the models to handle the raw results scraped from the upstream search
+//! engines before they have been aggregated together.
+
+use std::collections::HashMap;
+
+use url::Url;
+
+/// Query parameter prefixes added by trackers/referrers that don't affect which page loads, and
+/// so shouldn't make two otherwise-identical URLs compare as different.
+const TRACKING_PARAM_PREFIXES: &[&str] = &["utm_"];
+/// Exact-match query parameters added by trackers/referrers for the same reason as
+/// `TRACKING_PARAM_PREFIXES`.
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "msclkid", "ref", "ref_src"];
+
+/// A struct to hold the raw scraped search result from an upstream search engine before it has
+/// been merged with results from the other upstream search engines.
+#[derive(Clone)]
+pub struct RawSearchResult {
+    /// The title of the search result.
+    pub title: String,
+    /// The url which is accessed when the search result is clicked.
+    pub visiting_url: String,
+    /// The description of the search result.
+    pub description: String,
+    /// The names of the upstream engines from which this result was scraped.
+    pub engine: Vec<String>,
+}
+
+impl RawSearchResult {
+    /// Constructs a new `RawSearchResult`.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The title of the search result.
+    /// * `visiting_url` - The url which is accessed when the search result is clicked.
+    /// * `description` - The description of the search result.
+    /// * `engine` - The names of the upstream engines from which this result was scraped.
+    pub fn new(
+        title: String,
+        visiting_url: String,
+        description: String,
+        engine: Vec<String>,
+    ) -> Self {
+        RawSearchResult {
+            title,
+            visiting_url,
+            description,
+            engine,
+        }
+    }
+}
+
+/// Canonicalizes `url` so that the same page, linked from different engines (`http` vs `https`,
+/// host casing, a trailing slash, a `www.` prefix, tracking query params, a default port),
+/// collapses to the same key when inserted into the results map. Redirect links that an engine
+/// wraps around the real url are not unwrapped. Falls back to returning `url` unchanged if it
+/// fails to parse.
+fn canonicalize_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+
+    let _ = parsed.set_scheme("https");
+
+    // Only drop the port when it's the scheme's own default (e.g. the `:443` in
+    // `https://example.com:443/foo`) — an explicit, non-default port (e.g. `:8443`) points at a
+    // genuinely different resource and must be preserved.
+    if parsed.port() == Some(443) {
+        let _ = parsed.set_port(None);
+    }
+
+    if let Some(host) = parsed.host_str() {
+        let host = host.to_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+        let _ = parsed.set_host(Some(&host));
+    }
+
+    let retained_pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| {
+            !TRACKING_PARAMS.contains(&key.as_ref())
+                && !TRACKING_PARAM_PREFIXES
+                    .iter()
+                    .any(|prefix| key.starts_with(prefix))
+        })
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    if retained_pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(retained_pairs);
+    }
+
+    let path = parsed.path().trim_end_matches('/').to_string();
+    parsed.set_path(if path.is_empty() { "/" } else { &path });
+
+    parsed.into()
+}
+
+/// Inserts `result` into `results`, keyed by its canonicalized url instead of the raw, possibly
+/// engine-wrapped `href` that was scraped. If a result with the same canonical url is already
+/// present (e.g. reported by a different engine), the incoming result's engines are merged into
+/// the existing one rather than the duplicate silently overwriting it, so the final result
+/// carries accurate "found on N engines" provenance.
+pub fn insert_canonicalized(results: &mut HashMap<String, RawSearchResult>, result: RawSearchResult) {
+    let canonical_url = canonicalize_url(&result.visiting_url);
+
+    results
+        .entry(canonical_url)
+        .and_modify(|existing| {
+            for engine in &result.engine {
+                if !existing.engine.contains(engine) {
+                    existing.engine.push(engine.clone());
+                }
+            }
+        })
+        .or_insert(result);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(visiting_url: &str, engine: &str) -> RawSearchResult {
+        RawSearchResult::new(
+            "title".to_string(),
+            visiting_url.to_string(),
+            "description".to_string(),
+            vec![engine.to_string()],
+        )
+    }
+
+    #[test]
+    fn strips_default_port_but_keeps_a_distinct_one() {
+        assert_eq!(
+            canonicalize_url("https://example.com:443/foo"),
+            canonicalize_url("https://example.com/foo"),
+        );
+
+        assert_ne!(
+            canonicalize_url("https://example.com:8443/foo"),
+            canonicalize_url("https://example.com/foo"),
+        );
+    }
+
+    #[test]
+    fn normalizes_scheme_host_casing_www_and_trailing_slash() {
+        let a = canonicalize_url("http://WWW.Example.com/foo/");
+        let b = canonicalize_url("https://example.com/foo");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn strips_tracking_params_but_keeps_others() {
+        let a = canonicalize_url("https://example.com/foo?utm_source=x&fbclid=y&q=rust");
+        let b = canonicalize_url("https://example.com/foo?q=rust");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn insert_canonicalized_merges_engines_for_the_same_canonical_url() {
+        let mut results = HashMap::new();
+
+        insert_canonicalized(&mut results, raw("https://example.com/foo", "searx"));
+        insert_canonicalized(&mut results, raw("https://www.example.com/foo/", "bing"));
+
+        assert_eq!(results.len(), 1);
+        let merged = results.values().next().unwrap();
+        assert_eq!(merged.engine, vec!["searx".to_string(), "bing".to_string()]);
+    }
+
+    #[test]
+    fn insert_canonicalized_keeps_distinct_ports_separate() {
+        let mut results = HashMap::new();
+
+        insert_canonicalized(&mut results, raw("https://example.com/foo", "searx"));
+        insert_canonicalized(&mut results, raw("https://example.com:8443/foo", "bing"));
+
+        assert_eq!(results.len(), 2);
+    }
+}
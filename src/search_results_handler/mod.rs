@@ -0,0 +1,4 @@
+//! This module provides the models used to aggregate and deduplicate the results scraped from
+//! the different upstream search engines.
+
+pub mod aggregation_models;